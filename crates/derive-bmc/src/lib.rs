@@ -0,0 +1,110 @@
+//! `#[derive(Bmc)]` generates the backend model controller for an entity and
+//! delegates its `create`/`get`/`list`/`delete` methods to the generic
+//! `model::base::*` helpers, so each entity declares its table instead of
+//! hand-writing the CRUD methods. The generated methods take
+//! `<Entity>ForCreate` and `<Entity>Filter` types, which must be defined next
+//! to the entity.
+//!
+//! ```ignore
+//! #[derive(Clone, Debug, Fields, FromRow, Serialize, Bmc)]
+//! #[bmc(table = "task")]
+//! pub struct Task {
+//!     pub id: i64,
+//!     pub title: String,
+//! }
+//! // expands to `pub struct TaskBmc;` plus the delegating impl.
+//! ```
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, DeriveInput, LitStr};
+
+#[proc_macro_derive(Bmc, attributes(bmc))]
+pub fn derive_bmc(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+
+	let entity = &input.ident;
+	let bmc = format_ident!("{}Bmc", entity);
+	let for_create = format_ident!("{}ForCreate", entity);
+	let filter = format_ident!("{}Filter", entity);
+
+	let table = match table_name(&input) {
+		Ok(table) => table,
+		Err(err) => return err.to_compile_error().into(),
+	};
+
+	let expanded = quote! {
+		pub struct #bmc;
+
+		impl crate::model::base::DbBmc for #bmc {
+			const TABLE: &'static str = #table;
+		}
+
+		impl #bmc {
+			pub async fn create(
+				ctx: &crate::ctx::Ctx,
+				mm: &crate::model::ModelManager,
+				data: #for_create,
+			) -> crate::model::Result<i64> {
+				crate::model::base::create::<Self, _>(ctx, mm, data).await
+			}
+
+			pub async fn get(
+				ctx: &crate::ctx::Ctx,
+				mm: &crate::model::ModelManager,
+				id: i64,
+			) -> crate::model::Result<#entity> {
+				crate::model::base::get::<Self, _>(ctx, mm, id).await
+			}
+
+			pub async fn list(
+				ctx: &crate::ctx::Ctx,
+				mm: &crate::model::ModelManager,
+				filter: Option<#filter>,
+				list_options: Option<crate::model::base::ListOptions>,
+			) -> crate::model::Result<Vec<#entity>> {
+				crate::model::base::list::<Self, _, _>(ctx, mm, filter, list_options)
+					.await
+			}
+
+			pub async fn delete(
+				ctx: &crate::ctx::Ctx,
+				mm: &crate::model::ModelManager,
+				id: i64,
+			) -> crate::model::Result<()> {
+				crate::model::base::delete::<Self>(ctx, mm, id).await
+			}
+		}
+	};
+
+	expanded.into()
+}
+
+/// Extract the table name from `#[bmc(table = "...")]`.
+fn table_name(input: &DeriveInput) -> syn::Result<String> {
+	let attr = input
+		.attrs
+		.iter()
+		.find(|attr| attr.path().is_ident("bmc"))
+		.ok_or_else(|| {
+			syn::Error::new_spanned(
+				&input.ident,
+				"missing `#[bmc(table = \"...\")]` attribute",
+			)
+		})?;
+
+	let mut table = None;
+	attr.parse_nested_meta(|meta| {
+		if meta.path.is_ident("table") {
+			let value: LitStr = meta.value()?.parse()?;
+			table = Some(value.value());
+			Ok(())
+		} else {
+			Err(meta.error("unknown `bmc` attribute key"))
+		}
+	})?;
+
+	table.ok_or_else(|| {
+		syn::Error::new_spanned(attr, "`#[bmc]` requires `table = \"...\"`")
+	})
+}
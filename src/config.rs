@@ -1,11 +1,24 @@
+use crate::crypt::token::TokenScheme;
 use crate::{Error, Result};
-use std::{env, str::FromStr, sync::OnceLock};
+use arc_swap::{ArcSwap, Guard};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::{env, fs, str::FromStr, thread};
 
 pub struct Config {
 	// -- Crypt
 	pub pwd_key: Vec<u8>,
 
-	pub token_key: Vec<u8>,
+	pub token_scheme: TokenScheme,
+	/// Ordered `key_id -> key` map for symmetric signing. Older keys stay
+	/// valid for verification until operators drop them, enabling
+	/// zero-downtime rotation. Newest (active) key first.
+	pub token_keys: Vec<(String, Vec<u8>)>,
+	/// Id of the key in `token_keys` used to mint new tokens.
+	pub active_token_key_id: String,
+	pub token_private_key: Vec<u8>,
+	pub token_public_key: Vec<u8>,
 	pub token_duration_sec: f64,
 
 	// -- Db
@@ -15,28 +28,226 @@ pub struct Config {
 	pub web_folder: String,
 }
 
-pub fn config() -> &'static Config {
-	static INSTANCE: OnceLock<Config> = OnceLock::new();
+/// Returns a cheap guard over the current `Config`. The underlying value can
+/// be swapped at runtime (see [`Config::reload`]), so callers should read
+/// through `config()` each time rather than caching the reference.
+pub fn config() -> Guard<Arc<Config>> {
+	store().load()
+}
+
+fn store() -> &'static ArcSwap<Config> {
+	static INSTANCE: OnceLock<ArcSwap<Config>> = OnceLock::new();
 
 	INSTANCE.get_or_init(|| {
-		Config::load_from_env().unwrap_or_else(|err| {
+		let config = Config::load().unwrap_or_else(|err| {
 			panic!("FATAL - WHILE LOADING CONFIG - Cause: {err:?}")
-		})
+		});
+		let store = ArcSwap::from_pointee(config);
+		spawn_config_watcher();
+		store
 	})
 }
 
 impl Config {
+	/// Load the initial config from `SERVICE_CONFIG_FILE` when set, otherwise
+	/// from the environment variables.
+	fn load() -> Result<Config> {
+		match env::var("SERVICE_CONFIG_FILE") {
+			Ok(path) => Config::load_from_file(&path),
+			Err(_) => Config::load_from_env(),
+		}
+	}
+
+	/// Re-read the current source and atomically swap in the new config,
+	/// keeping the previous one on any parse error. Safe to wire to SIGHUP or
+	/// an admin endpoint for an explicit reload.
+	pub fn reload() -> Result<()> {
+		let config = Config::load()?;
+		store().store(Arc::new(config));
+		Ok(())
+	}
+
+	fn load_from_file(path: &str) -> Result<Config> {
+		let content = fs::read_to_string(path)
+			.map_err(|_| Error::ConfigMissingEnv("SERVICE_CONFIG_FILE"))?;
+		let file: ConfigFile = toml::from_str(&content)
+			.map_err(|_| Error::ConfigWrongFormat("SERVICE_CONFIG_FILE"))?;
+		file.try_into()
+	}
+
 	fn load_from_env() -> Result<Config> {
+		let token_scheme = get_env_parse("SERVICE_TOKEN_SCHEME")
+			.unwrap_or(TokenScheme::HmacSha512);
+
+		// HMAC keys are only required by the symmetric scheme; a pure-Ed25519
+		// deployment has no HMAC key map to supply.
+		let (token_keys, active_token_key_id) = match token_scheme {
+			TokenScheme::HmacSha512 => (
+				parse_token_keys(&get_env("SERVICE_TOKEN_KEYS")?)
+					.ok_or(Error::ConfigWrongFormat("SERVICE_TOKEN_KEYS"))?,
+				get_env("SERVICE_ACTIVE_TOKEN_KEY")?,
+			),
+			TokenScheme::Ed25519 => (Vec::new(), String::new()),
+		};
+
 		Ok(Config {
 			pwd_key: get_env_b64u_as_u8s("SERVICE_PWD_KEY")?,
-			token_key: get_env_b64u_as_u8s("SERVICE_TOKEN_KEY")?,
+			token_scheme,
+			token_keys,
+			active_token_key_id,
+			// Only needed for the `Ed25519` scheme; default to empty otherwise.
+			token_private_key: get_env_b64u_as_u8s("SERVICE_TOKEN_PRIVATE_KEY")
+				.unwrap_or_default(),
+			token_public_key: get_env_b64u_as_u8s("SERVICE_TOKEN_PUBLIC_KEY")
+				.unwrap_or_default(),
 			token_duration_sec: get_env_parse("SERVICE_TOKEN_DURATION_SEC")?,
 			web_folder: get_env("SERVICE_WEB_FOLDER")?,
 			db_url: get_env("SERVICE_DB_URL")?,
 		})
 	}
+
+	/// Look up a signing key by its id, returning `None` if the id has been
+	/// rotated out of the map.
+	pub fn token_key(&self, kid: &str) -> Option<&[u8]> {
+		self.token_keys
+			.iter()
+			.find(|(id, _)| id == kid)
+			.map(|(_, key)| key.as_slice())
+	}
+
+	/// The currently active `(key_id, key)` used to mint new tokens.
+	pub fn active_token_key(&self) -> Result<(&str, &[u8])> {
+		self.token_key(&self.active_token_key_id)
+			.map(|key| (self.active_token_key_id.as_str(), key))
+			.ok_or(Error::ConfigWrongFormat("SERVICE_ACTIVE_TOKEN_KEY"))
+	}
+}
+
+// region:    --- Config File
+
+/// TOML representation of the config, with all key material as base64url
+/// strings. Converted into [`Config`] by decoding those fields.
+#[derive(Deserialize)]
+struct ConfigFile {
+	pwd_key: String,
+	token_scheme: Option<String>,
+	token_keys: Option<String>,
+	active_token_key: Option<String>,
+	token_private_key: Option<String>,
+	token_public_key: Option<String>,
+	token_duration_sec: f64,
+	db_url: String,
+	web_folder: String,
+}
+
+impl TryFrom<ConfigFile> for Config {
+	type Error = Error;
+
+	fn try_from(file: ConfigFile) -> Result<Config> {
+		let decode = |name, s: &str| {
+			base64_url::decode(s).map_err(|_| Error::ConfigWrongFormat(name))
+		};
+
+		let token_scheme = file
+			.token_scheme
+			.as_deref()
+			.map(TokenScheme::from_str)
+			.transpose()
+			.map_err(|_| Error::ConfigWrongFormat("token_scheme"))?
+			.unwrap_or(TokenScheme::HmacSha512);
+
+		// HMAC keys are only required by the symmetric scheme.
+		let (token_keys, active_token_key_id) = match token_scheme {
+			TokenScheme::HmacSha512 => (
+				parse_token_keys(
+					file.token_keys
+						.as_deref()
+						.ok_or(Error::ConfigWrongFormat("token_keys"))?,
+				)
+				.ok_or(Error::ConfigWrongFormat("token_keys"))?,
+				file.active_token_key
+					.ok_or(Error::ConfigWrongFormat("active_token_key"))?,
+			),
+			TokenScheme::Ed25519 => (Vec::new(), String::new()),
+		};
+
+		Ok(Config {
+			pwd_key: decode("pwd_key", &file.pwd_key)?,
+			token_scheme,
+			token_keys,
+			active_token_key_id,
+			token_private_key: file
+				.token_private_key
+				.map(|s| decode("token_private_key", &s))
+				.transpose()?
+				.unwrap_or_default(),
+			token_public_key: file
+				.token_public_key
+				.map(|s| decode("token_public_key", &s))
+				.transpose()?
+				.unwrap_or_default(),
+			token_duration_sec: file.token_duration_sec,
+			db_url: file.db_url,
+			web_folder: file.web_folder,
+		})
+	}
+}
+
+// endregion: --- Config File
+
+// region:    --- File Watcher
+
+/// Watch `SERVICE_CONFIG_FILE` (when set) and reload on change, keeping the
+/// running config if the new file fails to parse.
+fn spawn_config_watcher() {
+	let Ok(path) = env::var("SERVICE_CONFIG_FILE") else {
+		return;
+	};
+
+	thread::spawn(move || {
+		use notify::event::{EventKind, ModifyKind};
+		use notify::{RecursiveMode, Watcher};
+
+		let (tx, rx) = std::sync::mpsc::channel();
+		let mut watcher = match notify::recommended_watcher(tx) {
+			Ok(watcher) => watcher,
+			Err(err) => {
+				tracing::error!("CONFIG WATCHER - cannot create watcher: {err}");
+				return;
+			}
+		};
+
+		if let Err(err) =
+			watcher.watch(Path::new(&path), RecursiveMode::NonRecursive)
+		{
+			tracing::error!("CONFIG WATCHER - cannot watch {path}: {err}");
+			return;
+		}
+
+		for event in rx {
+			// Only react to content changes; ignore access/metadata noise.
+			let relevant = matches!(
+				&event,
+				Ok(ev) if ev.kind.is_create()
+					|| (ev.kind.is_modify()
+						&& !matches!(ev.kind, EventKind::Modify(ModifyKind::Metadata(_))))
+			);
+			if !relevant {
+				continue;
+			}
+
+			match Config::reload() {
+				Ok(_) => tracing::info!("CONFIG WATCHER - reloaded {path}"),
+				Err(err) => tracing::error!(
+					"CONFIG WATCHER - reload failed, keeping old config: {err:?}"
+				),
+			}
+		}
+	});
 }
 
+// endregion: --- File Watcher
+
 fn get_env(name: &'static str) -> Result<String> {
 	env::var(name).map_err(|_| Error::ConfigMissingEnv(name))
 }
@@ -49,3 +260,14 @@ fn get_env_parse<T: FromStr>(name: &'static str) -> Result<T> {
 fn get_env_b64u_as_u8s(name: &'static str) -> Result<Vec<u8>> {
 	base64_url::decode(&get_env(name)?).map_err(|_| Error::ConfigWrongFormat(name))
 }
+
+/// Parse a `kid:b64u,kid:b64u` list into an ordered `key_id -> key` map.
+fn parse_token_keys(raw: &str) -> Option<Vec<(String, Vec<u8>)>> {
+	raw.split(',')
+		.map(|entry| {
+			let (kid, key_b64u) = entry.split_once(':')?;
+			let key = base64_url::decode(key_b64u).ok()?;
+			Some((kid.to_string(), key))
+		})
+		.collect()
+}
@@ -12,11 +12,16 @@ pub enum Error {
 
 	// -- Token,
 	TokenInvalidFormat,
+	TokenInvalidScheme,
+	TokenCannotDecodeKid,
 	TokenCannotDecodeIdent,
 	TokenCannotDecodeExp,
 	TokenSignatureNotMatching,
+	TokenUnknownKeyId,
+	TokenKeyInvalid,
 	TokenExpNotIso,
 	TokenExpired,
+	TokenRevoked,
 }
 
 impl core::fmt::Display for Error {
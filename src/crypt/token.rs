@@ -1,4 +1,8 @@
+use std::collections::HashSet;
 use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 
 use crate::config;
 use crate::crypt::{encrypt_into_b64u, EncryptContent, Error, Result};
@@ -6,11 +10,45 @@ use crate::utils::{
 	b64u_decode, b64u_encode, now_utc, now_utc_plus_sec_str, parse_utc,
 };
 
+/// `kid` recorded for Ed25519 tokens. The scheme has no HMAC key map to index,
+/// so validation keys off the scheme's single public key rather than this id.
+const ED25519_KID: &str = "ed25519";
+
+// region:      --- Token Scheme
+
+/// Signing scheme used to produce and verify the token signature.
+///
+/// `HmacSha512` is the original symmetric scheme (the signer and the
+/// verifier share `token_key`). `Ed25519` is asymmetric: the auth service
+/// signs with `token_private_key` while downstream services only need
+/// `token_public_key` to validate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenScheme {
+	HmacSha512,
+	Ed25519,
+}
+
+impl FromStr for TokenScheme {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s {
+			"HmacSha512" | "hmac_sha512" => Ok(Self::HmacSha512),
+			"Ed25519" | "ed25519" => Ok(Self::Ed25519),
+			_ => Err(Error::TokenInvalidScheme),
+		}
+	}
+}
+
+// endregion:   --- Token Scheme
+
 // region:      --- Token Type
 
-/// String format: `ident_b64u.exp_b64u.sign_b64u`
+/// String format: `kid_b64u.ident_b64u.exp_b64u.sign_b64u`
 #[derive(Debug, PartialEq)]
 pub struct Token {
+	/// Id of the key that produced the signature (for key rotation)
+	pub kid: String,
 	/// Identifier (username for example)
 	pub ident: String,
 	/// Expiration date in Rfc3339
@@ -25,12 +63,14 @@ impl FromStr for Token {
 	fn from_str(token_str: &str) -> std::prelude::v1::Result<Self, Self::Err> {
 		let splits: Vec<&str> = token_str.split('.').collect();
 
-		let [ident_b64u, exp_b64u, sign_b64u] = splits[..] else {
-			// splits.len() != 3
+		let [kid_b64u, ident_b64u, exp_b64u, sign_b64u] = splits[..] else {
+			// splits.len() != 4
 			return Err(Error::TokenInvalidFormat);
 		};
 
 		Ok(Self {
+			kid: b64u_decode(kid_b64u)
+				.map_err(|_| Error::TokenCannotDecodeKid)?,
 			ident: b64u_decode(ident_b64u)
 				.map_err(|_| Error::TokenCannotDecodeIdent)?,
 			exp: b64u_decode(exp_b64u).map_err(|_| Error::TokenCannotDecodeExp)?,
@@ -43,7 +83,8 @@ impl std::fmt::Display for Token {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(
 			f,
-			"{}.{}.{}",
+			"{}.{}.{}.{}",
+			b64u_encode(&self.kid),
 			b64u_encode(&self.ident),
 			b64u_encode(&self.exp),
 			self.sign_b64u
@@ -57,24 +98,96 @@ impl std::fmt::Display for Token {
 
 pub fn generate_web_token(user: &str, salt: &str) -> Result<Token> {
 	let config = &config();
-	_generate_token(user, config.token_duration_sec, salt, &config.token_key)
+	generate_token(user, config.token_duration_sec, salt)
 }
 
 pub fn validate_web_token(origin_token: &Token, salt: &str) -> Result<()> {
-	let config = &config();
-	_validate_token_sign_and_exp(origin_token, salt, &config.token_key)?;
+	validate_token(origin_token, salt)?;
+
+	// -- Consult the emergency revocation denylist.
+	if is_revoked(&origin_token.ident, &origin_token.sign_b64u) {
+		return Err(Error::TokenRevoked);
+	}
 
 	Ok(())
 }
 
+/// Mint a signed token for `ident` with an arbitrary lifetime. Used for web
+/// tokens (short) and refresh tokens (long) alike.
+pub fn generate_token(
+	ident: &str,
+	duration_sec: f64,
+	salt: &str,
+) -> Result<Token> {
+	let config = &config();
+	let (kid, key): (&str, &[u8]) = match config.token_scheme {
+		TokenScheme::HmacSha512 => config
+			.active_token_key()
+			.map_err(|_| Error::TokenUnknownKeyId)?,
+		// Ed25519 has no HMAC key map, so the `kid` is a fixed scheme tag
+		// rather than a lookup into `token_keys`.
+		TokenScheme::Ed25519 => (ED25519_KID, &config.token_private_key),
+	};
+	_generate_token(ident, duration_sec, salt, kid, config.token_scheme, key)
+}
+
+/// Validate a token's signature and expiration (without the web denylist).
+pub fn validate_token(origin_token: &Token, salt: &str) -> Result<()> {
+	let config = &config();
+	let key: &[u8] = match config.token_scheme {
+		// Look up exactly the key that signed this token, so tokens minted
+		// with a now-retired key keep validating until the key is dropped.
+		TokenScheme::HmacSha512 => config
+			.token_key(&origin_token.kid)
+			.ok_or(Error::TokenUnknownKeyId)?,
+		TokenScheme::Ed25519 => &config.token_public_key,
+	};
+	_validate_token_sign_and_exp(origin_token, salt, config.token_scheme, key)
+}
+
 // endregion:   --- Web Token Gen and Validation
 
+// region:      --- Token Revocation Denylist
+
+/// Process-local *fast-path* denylist of `(ident, sign_b64u)` pairs that
+/// `validate_web_token` rejects even while still within their expiry window.
+///
+/// NOTE: This cache is per-process only — it does not survive a restart and is
+/// not shared across replicas. The database keeps a persisted `revoked` flag
+/// only on stored refresh-token rows; `model::token::is_revoked` and
+/// `model::token::validate_web_token` consult both that flag and this cache.
+fn revocations() -> &'static Mutex<HashSet<(String, String)>> {
+	static INSTANCE: OnceLock<Mutex<HashSet<(String, String)>>> =
+		OnceLock::new();
+	INSTANCE.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Add a `(ident, sign_b64u)` pair to the revocation denylist.
+pub fn revoke_token(ident: &str, sign_b64u: &str) {
+	revocations()
+		.lock()
+		.unwrap()
+		.insert((ident.to_string(), sign_b64u.to_string()));
+}
+
+/// Whether a `(ident, sign_b64u)` pair has been revoked.
+pub fn is_revoked(ident: &str, sign_b64u: &str) -> bool {
+	revocations()
+		.lock()
+		.unwrap()
+		.contains(&(ident.to_string(), sign_b64u.to_string()))
+}
+
+// endregion:   --- Token Revocation Denylist
+
 // region:      --- (private) Token Gen and Validation
 
 fn _generate_token(
 	ident: &str,
 	duration_sec: f64,
 	salt: &str,
+	kid: &str,
+	scheme: TokenScheme,
 	key: &[u8],
 ) -> Result<Token> {
 	// -- Compute the first two components
@@ -82,9 +195,10 @@ fn _generate_token(
 	let exp = now_utc_plus_sec_str(duration_sec);
 
 	// -- Sign the two first two components
-	let sign_b64u = _token_sign_into_b64u(&ident, &exp, salt, key)?;
+	let sign_b64u = _token_sign_into_b64u(&ident, &exp, salt, scheme, key)?;
 
 	Ok(Token {
+		kid: kid.to_string(),
 		ident,
 		exp,
 		sign_b64u,
@@ -94,14 +208,44 @@ fn _generate_token(
 fn _validate_token_sign_and_exp(
 	origin_token: &Token,
 	salt: &str,
+	scheme: TokenScheme,
 	key: &[u8],
 ) -> Result<()> {
 	// -- Validate signature
-	let new_sign_b64u =
-		_token_sign_into_b64u(&origin_token.ident, &origin_token.exp, salt, key)?;
-
-	if new_sign_b64u != origin_token.sign_b64u {
-		return Err(Error::TokenSignatureNotMatching);
+	match scheme {
+		// Symmetric: recompute the signature and compare.
+		TokenScheme::HmacSha512 => {
+			let new_sign_b64u = _token_sign_into_b64u(
+				&origin_token.ident,
+				&origin_token.exp,
+				salt,
+				scheme,
+				key,
+			)?;
+
+			if new_sign_b64u != origin_token.sign_b64u {
+				return Err(Error::TokenSignatureNotMatching);
+			}
+		}
+		// Asymmetric: verify the signature against the public key.
+		TokenScheme::Ed25519 => {
+			let verifying_key = _ed25519_verifying_key(key)?;
+			let sign = b64u_decode(&origin_token.sign_b64u)
+				.map_err(|_| Error::TokenSignatureNotMatching)?;
+			let sign: [u8; Signature::BYTE_SIZE] = sign
+				.try_into()
+				.map_err(|_| Error::TokenSignatureNotMatching)?;
+			let signature = Signature::from_bytes(&sign);
+
+			let content = _ed25519_sign_content(
+				&origin_token.ident,
+				&origin_token.exp,
+				salt,
+			);
+			verifying_key
+				.verify(content.as_bytes(), &signature)
+				.map_err(|_| Error::TokenSignatureNotMatching)?;
+		}
 	}
 
 	// -- Validate expiration
@@ -121,18 +265,53 @@ fn _token_sign_into_b64u(
 	ident: &str,
 	exp: &str,
 	salt: &str,
+	scheme: TokenScheme,
 	key: &[u8],
 ) -> Result<String> {
-	let content = format!("{}.{}", b64u_encode(ident), b64u_encode(exp));
-	let signature = encrypt_into_b64u(
-		key,
-		&EncryptContent {
-			content,
-			salt: salt.to_string(),
-		},
-	);
+	match scheme {
+		TokenScheme::HmacSha512 => {
+			let content = _token_sign_content(ident, exp);
+			encrypt_into_b64u(
+				key,
+				&EncryptContent {
+					content,
+					salt: salt.to_string(),
+				},
+			)
+		}
+		TokenScheme::Ed25519 => {
+			let signing_key = _ed25519_signing_key(key)?;
+			let content = _ed25519_sign_content(ident, exp, salt);
+			let signature = signing_key.sign(content.as_bytes());
+			Ok(b64u_encode(signature.to_bytes()))
+		}
+	}
+}
+
+/// The signed content shared by both schemes: `ident_b64u.exp_b64u`.
+fn _token_sign_content(ident: &str, exp: &str) -> String {
+	format!("{}.{}", b64u_encode(ident), b64u_encode(exp))
+}
+
+/// Ed25519 folds `salt` into the signed content, so the salt binds the
+/// signature just as it does for HMAC (which binds it via the
+/// `EncryptContent` salt field).
+fn _ed25519_sign_content(ident: &str, exp: &str, salt: &str) -> String {
+	format!("{}.{}", _token_sign_content(ident, exp), b64u_encode(salt))
+}
 
-	signature
+/// Build an Ed25519 signing key from a base64url-decoded 32-byte seed.
+fn _ed25519_signing_key(key: &[u8]) -> Result<SigningKey> {
+	let seed: [u8; 32] =
+		key.try_into().map_err(|_| Error::TokenKeyInvalid)?;
+	Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Build an Ed25519 verifying key from base64url-decoded 32 bytes.
+fn _ed25519_verifying_key(key: &[u8]) -> Result<VerifyingKey> {
+	let bytes: [u8; 32] =
+		key.try_into().map_err(|_| Error::TokenKeyInvalid)?;
+	VerifyingKey::from_bytes(&bytes).map_err(|_| Error::TokenKeyInvalid)
 }
 
 // endregion:   --- (private) Token Gen and Validation
@@ -147,8 +326,9 @@ mod tests {
 	fn test_token_display_ok() -> Result<()> {
 		// -- Fixtures
 		let fx_token_str =
-			"ZngtaWRlbnQtMDE.MjAyMy0wNS0xN1QxNTo4MDowMFo.some-sign-b64u-encoded";
+			"djE.ZngtaWRlbnQtMDE.MjAyMy0wNS0xN1QxNTo4MDowMFo.some-sign-b64u-encoded";
 		let fx_token = Token {
+			kid: "v1".to_string(),
 			ident: "fx-ident-01".to_string(),
 			exp: "2023-05-17T15:80:00Z".to_string(),
 			sign_b64u: "some-sign-b64u-encoded".to_string(),
@@ -164,8 +344,9 @@ mod tests {
 	fn test_token_from_str_ok() -> Result<()> {
 		// -- Fixtures
 		let fx_token_str =
-			"ZngtaWRlbnQtMDE.MjAyMy0wNS0xN1QxNTo4MDowMFo.some-sign-b64u-encoded";
+			"djE.ZngtaWRlbnQtMDE.MjAyMy0wNS0xN1QxNTo4MDowMFo.some-sign-b64u-encoded";
 		let fx_token = Token {
+			kid: "v1".to_string(),
 			ident: "fx-ident-01".to_string(),
 			exp: "2023-05-17T15:80:00Z".to_string(),
 			sign_b64u: "some-sign-b64u-encoded".to_string(),
@@ -186,9 +367,16 @@ mod tests {
 		let fx_user = "user_one";
 		let fx_salt = "pepper";
 		let fx_duration_sec = 0.02; // 20ms
-		let token_key = &config().token_key;
-		let fx_token =
-			_generate_token(fx_user, fx_duration_sec, fx_salt, token_key)?;
+		let config = config();
+		let (fx_kid, token_key) = config.active_token_key().unwrap();
+		let fx_token = _generate_token(
+			fx_user,
+			fx_duration_sec,
+			fx_salt,
+			fx_kid,
+			TokenScheme::HmacSha512,
+			token_key,
+		)?;
 
 		// -- Exec
 		thread::sleep(Duration::from_millis(10));
@@ -206,9 +394,16 @@ mod tests {
 		let fx_user = "user_one";
 		let fx_salt = "pepper";
 		let fx_duration_sec = 0.01; // 10ms
-		let token_key = &config().token_key;
-		let fx_token =
-			_generate_token(fx_user, fx_duration_sec, fx_salt, token_key)?;
+		let config = config();
+		let (fx_kid, token_key) = config.active_token_key().unwrap();
+		let fx_token = _generate_token(
+			fx_user,
+			fx_duration_sec,
+			fx_salt,
+			fx_kid,
+			TokenScheme::HmacSha512,
+			token_key,
+		)?;
 
 		// -- Exec
 		thread::sleep(Duration::from_millis(20));
@@ -222,4 +417,135 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_validate_web_token_err_unknown_kid() -> Result<()> {
+		// -- Fixtures: a token whose kid is not in the current key map.
+		let fx_token = Token {
+			kid: "does-not-exist".to_string(),
+			ident: "user_one".to_string(),
+			exp: now_utc_plus_sec_str(60.0),
+			sign_b64u: "irrelevant".to_string(),
+		};
+
+		// -- Exec
+		let res = validate_web_token(&fx_token, "pepper");
+
+		// -- Check
+		assert!(
+			matches!(res, Err(Error::TokenUnknownKeyId)),
+			"Should have matched `Err(Error::TokenUnknownKeyId)` but was `{res:?}`"
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_token_hmac_old_key_still_valid() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_user = "user_one";
+		let fx_salt = "pepper";
+		let fx_old_key = b"old-signing-key-material";
+		let fx_new_key = b"new-active-key-material";
+
+		// Minted while "v1" was the active key.
+		let fx_token = _generate_token(
+			fx_user,
+			60.0,
+			fx_salt,
+			"v1",
+			TokenScheme::HmacSha512,
+			fx_old_key,
+		)?;
+
+		// -- Check: verifying against the still-present "v1" key succeeds...
+		_validate_token_sign_and_exp(
+			&fx_token,
+			fx_salt,
+			TokenScheme::HmacSha512,
+			fx_old_key,
+		)?;
+
+		// ...while the rotated-in active key rejects it.
+		let res = _validate_token_sign_and_exp(
+			&fx_token,
+			fx_salt,
+			TokenScheme::HmacSha512,
+			fx_new_key,
+		);
+		assert!(
+			matches!(res, Err(Error::TokenSignatureNotMatching)),
+			"Should have matched `Err(Error::TokenSignatureNotMatching)` but was `{res:?}`"
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_token_ed25519_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_user = "user_one";
+		let fx_salt = "pepper";
+		let fx_kid = "ed25519";
+		let fx_duration_sec = 0.02; // 20ms
+		let signing_key = SigningKey::from_bytes(&[1u8; 32]);
+		let verifying_key = signing_key.verifying_key();
+
+		// -- Exec
+		let fx_token = _generate_token(
+			fx_user,
+			fx_duration_sec,
+			fx_salt,
+			fx_kid,
+			TokenScheme::Ed25519,
+			&signing_key.to_bytes(),
+		)?;
+		let res = _validate_token_sign_and_exp(
+			&fx_token,
+			fx_salt,
+			TokenScheme::Ed25519,
+			&verifying_key.to_bytes(),
+		);
+
+		// -- Check
+		res?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_validate_token_ed25519_err_wrong_key() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_user = "user_one";
+		let fx_salt = "pepper";
+		let fx_kid = "ed25519";
+		let fx_duration_sec = 0.02; // 20ms
+		let signing_key = SigningKey::from_bytes(&[1u8; 32]);
+		// A different, unrelated key pair.
+		let wrong_key = SigningKey::from_bytes(&[2u8; 32]);
+
+		// -- Exec
+		let fx_token = _generate_token(
+			fx_user,
+			fx_duration_sec,
+			fx_salt,
+			fx_kid,
+			TokenScheme::Ed25519,
+			&signing_key.to_bytes(),
+		)?;
+		let res = _validate_token_sign_and_exp(
+			&fx_token,
+			fx_salt,
+			TokenScheme::Ed25519,
+			&wrong_key.verifying_key().to_bytes(),
+		);
+
+		// -- Check
+		assert!(
+			matches!(res, Err(Error::TokenSignatureNotMatching)),
+			"Should have matched `Err(Error::TokenSignatureNotMatching)` but was `{res:?}`"
+		);
+
+		Ok(())
+	}
 }
@@ -0,0 +1,178 @@
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::{Error, Result};
+use serde::Deserialize;
+use sqlb::HasFields;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, Postgres, QueryBuilder};
+
+/// Marker trait for entity controllers, carrying the backing table name used
+/// by the generic `base::*` CRUD helpers below.
+pub trait DbBmc {
+	const TABLE: &'static str;
+}
+
+/// Limit/offset/order-by knobs for `list`. `order_bys` entries are column
+/// names, optionally prefixed with `!` to sort descending (e.g. `!id`).
+#[derive(Debug, Default, Deserialize)]
+pub struct ListOptions {
+	pub limit: Option<i64>,
+	pub offset: Option<i64>,
+	pub order_bys: Option<Vec<String>>,
+}
+
+/// Implemented by per-entity filter types to contribute parameterized
+/// `WHERE` predicates and to declare which columns may appear in a filter or
+/// `ORDER BY`. Column validation keeps untrusted `order_bys` strings from
+/// reaching the SQL text.
+pub trait ListFilter {
+	/// Columns this entity allows in filters and order-by clauses.
+	fn columns() -> &'static [&'static str];
+	/// Append predicates onto `builder`, each prefixed with ` AND ` so they
+	/// can follow a `WHERE true` seed regardless of how many fire. Bound
+	/// values must be owned so the builder can outlive the filter.
+	fn push_where(
+		&self,
+		builder: &mut QueryBuilder<'static, Postgres>,
+	) -> Result<()>;
+}
+
+pub async fn create<MC, E>(_ctx: &Ctx, mm: &ModelManager, data: E) -> Result<i64>
+where
+	MC: DbBmc,
+	E: HasFields,
+{
+	let db = mm.db();
+
+	let fields = data.not_none_fields();
+	let (id,) = sqlb::insert()
+		.table(MC::TABLE)
+		.data(fields)
+		.returning(&["id"])
+		.fetch_one::<_, (i64,)>(db)
+		.await?;
+
+	Ok(id)
+}
+
+pub async fn get<MC, E>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+	E: HasFields,
+{
+	let db = mm.db();
+
+	let entity: E = sqlb::select()
+		.table(MC::TABLE)
+		.columns(E::field_names())
+		.and_where("id", "=", id)
+		.fetch_optional(db)
+		.await?
+		.ok_or(Error::EntityNotFound {
+			entity: MC::TABLE,
+			id,
+		})?;
+
+	Ok(entity)
+}
+
+pub async fn list<MC, E, F>(
+	_ctx: &Ctx,
+	mm: &ModelManager,
+	filter: Option<F>,
+	list_options: Option<ListOptions>,
+) -> Result<Vec<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+	F: ListFilter,
+{
+	let db = mm.db();
+
+	let mut query = list_query::<MC, F>(filter, list_options)?;
+	let entities = query.build_query_as::<E>().fetch_all(db).await?;
+
+	Ok(entities)
+}
+
+/// Assemble the `SELECT` statement for [`list`] from the filter and options,
+/// validating `order_bys` column names against the entity's allow-list. Split
+/// out from `list` so the generated SQL can be exercised without a database.
+pub fn list_query<MC, F>(
+	filter: Option<F>,
+	list_options: Option<ListOptions>,
+) -> Result<QueryBuilder<'static, Postgres>>
+where
+	MC: DbBmc,
+	F: ListFilter,
+{
+	// `WHERE true` lets every predicate append with a uniform ` AND `.
+	let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM ");
+	query.push(MC::TABLE).push(" WHERE true");
+
+	if let Some(filter) = filter {
+		filter.push_where(&mut query)?;
+	}
+
+	// -- Order by
+	match list_options.as_ref().and_then(|o| o.order_bys.as_ref()) {
+		Some(order_bys) if !order_bys.is_empty() => {
+			query.push(" ORDER BY ");
+			for (idx, order_by) in order_bys.iter().enumerate() {
+				let (column, desc) = match order_by.strip_prefix('!') {
+					Some(column) => (column, true),
+					None => (order_by.as_str(), false),
+				};
+				if !F::columns().contains(&column) {
+					return Err(Error::ListInvalidOrderBy(order_by.clone()));
+				}
+				if idx > 0 {
+					query.push(", ");
+				}
+				// Safe: `column` was matched against the allow-list above.
+				query.push(column);
+				if desc {
+					query.push(" DESC");
+				}
+			}
+		}
+		_ => {
+			query.push(" ORDER BY id");
+		}
+	}
+
+	// -- Limit / Offset
+	if let Some(options) = list_options.as_ref() {
+		if let Some(limit) = options.limit {
+			query.push(" LIMIT ").push_bind(limit);
+		}
+		if let Some(offset) = options.offset {
+			query.push(" OFFSET ").push_bind(offset);
+		}
+	}
+
+	Ok(query)
+}
+
+pub async fn delete<MC>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+	MC: DbBmc,
+{
+	let db = mm.db();
+
+	let count = sqlb::delete()
+		.table(MC::TABLE)
+		.and_where("id", "=", id)
+		.exec(db)
+		.await?;
+
+	if count == 0 {
+		return Err(Error::EntityNotFound {
+			entity: MC::TABLE,
+			id,
+		});
+	}
+
+	Ok(())
+}
@@ -0,0 +1,49 @@
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+pub enum Error {
+	EntityNotFound { entity: &'static str, id: i64 },
+
+	// -- List
+	ListInvalidOrderBy(String),
+
+	// -- Refresh Tokens
+	TokenNotFound,
+	TokenReused,
+	TokenRevoked,
+
+	// -- Modules
+	Crypt(crate::crypt::Error),
+
+	// -- Externals
+	#[serde(skip)]
+	Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+}
+
+// region:    --- Froms
+impl From<sqlx::Error> for Error {
+	fn from(val: sqlx::Error) -> Self {
+		Self::Sqlx(val)
+	}
+}
+
+impl From<crate::crypt::Error> for Error {
+	fn from(val: crate::crypt::Error) -> Self {
+		Self::Crypt(val)
+	}
+}
+// endregion: --- Froms
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
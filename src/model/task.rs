@@ -1,88 +1,147 @@
-use crate::ctx::Ctx;
+use crate::model::base::ListFilter;
 use crate::model::Result;
-use crate::model::{Error, ModelManager};
+use derive_bmc::Bmc;
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlb::Fields;
+use sqlx::{FromRow, Postgres, QueryBuilder};
 
-#[derive(Debug, Clone, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, Bmc)]
+#[bmc(table = "task")]
 pub struct Task {
 	pub id: i64,
 	pub title: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Fields, Deserialize)]
 pub struct TaskForCreate {
 	pub title: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Fields, Deserialize)]
 pub struct TaskForUpdate {
 	pub title: Option<String>,
 }
 
-// Task Backend Model Controller
-pub struct TaskBmc;
-
-impl TaskBmc {
-	pub async fn create(
-		_ctx: &Ctx,
-		mm: &ModelManager,
-		task_c: TaskForCreate,
-	) -> Result<i64> {
-		let db = mm.db();
-
-		let (id,) = sqlx::query_as::<_, (i64,)>(
-			"INSERT INTO task (title) values ($1) RETURNING id",
-		)
-		.bind(task_c.title)
-		.fetch_one(db)
-		.await?;
-
-		Ok(id)
-	}
+/// JSON-deserialized filter for [`TaskBmc::list`], one optional operator set
+/// per column. A missing field means "no constraint on this column".
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TaskFilter {
+	pub id: Option<IntOps>,
+	pub title: Option<StringOps>,
+}
 
-	pub async fn get(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Task> {
-		let db = mm.db();
+/// Operators for integer columns.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct IntOps {
+	pub eq: Option<i64>,
+	pub gt: Option<i64>,
+	pub lt: Option<i64>,
+	#[serde(rename = "in")]
+	pub in_: Option<Vec<i64>>,
+}
 
-		let task: Task = sqlx::query_as("SELECT * FROM task WHERE id = $1")
-			.bind(id)
-			.fetch_optional(db)
-			.await?
-			.ok_or(Error::EntityNotFound { entity: "task", id })?;
+/// Operators for text columns.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StringOps {
+	pub eq: Option<String>,
+	pub contains: Option<String>,
+	#[serde(rename = "in")]
+	pub in_: Option<Vec<String>>,
+}
 
-		Ok(task)
+impl ListFilter for TaskFilter {
+	fn columns() -> &'static [&'static str] {
+		&["id", "title"]
 	}
 
-	pub async fn list(_ctx: &Ctx, mm: &ModelManager) -> Result<Vec<Task>> {
-		let db = mm.db();
+	fn push_where(
+		&self,
+		builder: &mut QueryBuilder<'static, Postgres>,
+	) -> Result<()> {
+		if let Some(id) = &self.id {
+			if let Some(eq) = id.eq {
+				builder.push(" AND id = ").push_bind(eq);
+			}
+			if let Some(gt) = id.gt {
+				builder.push(" AND id > ").push_bind(gt);
+			}
+			if let Some(lt) = id.lt {
+				builder.push(" AND id < ").push_bind(lt);
+			}
+			if let Some(values) = &id.in_ {
+				push_in(builder, "id", values);
+			}
+		}
 
-		let tasks: Vec<Task> = sqlx::query_as("SELECT * FROM task ORDER BY id")
-			.fetch_all(db)
-			.await?;
+		if let Some(title) = &self.title {
+			if let Some(eq) = &title.eq {
+				builder.push(" AND title = ").push_bind(eq.clone());
+			}
+			if let Some(contains) = &title.contains {
+				// Escape LIKE metacharacters so `contains` is a literal
+				// substring match, not a wildcard pattern.
+				let pattern = format!("%{}%", escape_like(contains));
+				builder
+					.push(" AND title LIKE ")
+					.push_bind(pattern)
+					.push(" ESCAPE '\\'");
+			}
+			if let Some(values) = &title.in_ {
+				push_in(builder, "title", values);
+			}
+		}
 
-		Ok(tasks)
+		Ok(())
 	}
+}
 
-	pub async fn delete(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
-		let db = mm.db();
-
-		let count = sqlx::query("DELETE FROM task WHERE id = $1")
-			.bind(id)
-			.execute(db)
-			.await?
-			.rows_affected();
+/// Append a parameterized ` AND <column> IN (...)` clause.
+fn push_in<T>(
+	builder: &mut QueryBuilder<'static, Postgres>,
+	column: &str,
+	values: &[T],
+) where
+	T: Clone
+		+ Send
+		+ sqlx::Type<Postgres>
+		+ for<'q> sqlx::Encode<'q, Postgres>
+		+ 'static,
+{
+	if values.is_empty() {
+		return;
+	}
+	builder.push(format!(" AND {column} IN ("));
+	let mut separated = builder.separated(", ");
+	for value in values {
+		separated.push_bind(value.clone());
+	}
+	separated.push_unseparated(")");
+}
 
-		if count == 0 {
-			return Err(Error::EntityNotFound { entity: "task", id });
+/// Escape SQL `LIKE` metacharacters (`\`, `%`, `_`) using `\` as the escape
+/// character (see the `ESCAPE '\'` clause applied at the call site).
+fn escape_like(term: &str) -> String {
+	let mut escaped = String::with_capacity(term.len());
+	for ch in term.chars() {
+		if matches!(ch, '\\' | '%' | '_') {
+			escaped.push('\\');
 		}
-
-		Ok(())
+		escaped.push(ch);
 	}
+	escaped
 }
 
+// `TaskBmc` and its `create`/`get`/`list`/`delete` methods are generated by
+// `#[derive(Bmc)]` above, delegating to the generic `model::base::*` helpers.
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::ctx::Ctx;
+	use crate::model::Error;
 	use crate::_dev_utils;
 	use anyhow::Result;
 	use serial_test::serial;
@@ -173,7 +232,7 @@ mod tests {
 		_dev_utils::seed_tasks(&ctx, &mm, fx_titles).await?;
 
 		// -- Exec
-		let tasks = TaskBmc::list(&ctx, &mm).await?;
+		let tasks = TaskBmc::list(&ctx, &mm, None, None).await?;
 
 		// -- Check
 		let tasks: Vec<Task> = tasks
@@ -189,4 +248,61 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_list_query_assembly_ok() {
+		use crate::model::base::{self, ListOptions};
+
+		// -- Fixtures
+		let fx_filter = TaskFilter {
+			id: Some(IntOps {
+				gt: Some(10),
+				..Default::default()
+			}),
+			title: Some(StringOps {
+				contains: Some("foo".to_string()),
+				..Default::default()
+			}),
+		};
+		let fx_options = ListOptions {
+			limit: Some(20),
+			offset: Some(40),
+			order_bys: Some(vec!["!id".to_string(), "title".to_string()]),
+		};
+
+		// -- Exec
+		let mut query =
+			base::list_query::<TaskBmc, TaskFilter>(Some(fx_filter), Some(fx_options))
+				.unwrap();
+		let sql = query.sql();
+
+		// -- Check
+		assert!(sql.contains("FROM task"), "sql: {sql}");
+		assert!(sql.contains("id > "), "sql: {sql}");
+		assert!(sql.contains("title LIKE "), "sql: {sql}");
+		assert!(sql.contains("ORDER BY id DESC, title"), "sql: {sql}");
+		assert!(sql.contains("LIMIT "), "sql: {sql}");
+		assert!(sql.contains("OFFSET "), "sql: {sql}");
+	}
+
+	#[test]
+	fn test_list_query_err_invalid_order_by() {
+		use crate::model::base::{self, ListOptions};
+
+		// -- Fixtures
+		let fx_options = ListOptions {
+			order_bys: Some(vec!["bogus".to_string()]),
+			..Default::default()
+		};
+
+		// -- Exec
+		let res =
+			base::list_query::<TaskBmc, TaskFilter>(None, Some(fx_options));
+
+		// -- Check
+		assert!(
+			matches!(res, Err(Error::ListInvalidOrderBy(ref col)) if col == "bogus"),
+			"Should have matched `Err(Error::ListInvalidOrderBy(\"bogus\"))` but was `{res:?}`"
+		);
+	}
 }
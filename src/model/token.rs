@@ -0,0 +1,359 @@
+use crate::crypt::token::{self, Token};
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::model::{Error, Result};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Refresh tokens live far longer than web tokens so a session can be renewed
+/// without re-authenticating. 30 days.
+const REFRESH_DURATION_SEC: f64 = 60.0 * 60.0 * 24.0 * 30.0;
+
+// region:      --- Token Record
+
+/// A refresh token as persisted in the `token` table. `token_id` is the random
+/// opaque identifier carried as the token's `ident`; `chain_id` ties together
+/// every token minted from a single login so the whole chain can be revoked at
+/// once when replay is detected.
+#[derive(Debug, Clone, FromRow)]
+pub struct TokenRecord {
+	pub id: i64,
+	pub user_ident: String,
+	pub token_id: String,
+	pub sign_b64u: String,
+	pub exp: String,
+	pub chain_id: String,
+	pub used: bool,
+	pub revoked: bool,
+}
+
+struct TokenForCreate {
+	user_ident: String,
+	token_id: String,
+	sign_b64u: String,
+	exp: String,
+	chain_id: String,
+}
+
+// endregion:   --- Token Record
+
+// region:      --- TokenBmc
+
+pub struct TokenBmc;
+
+impl TokenBmc {
+	async fn create(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		token_c: TokenForCreate,
+	) -> Result<i64> {
+		let db = mm.db();
+
+		let (id,) = sqlx::query_as::<_, (i64,)>(
+			"INSERT INTO token
+				(user_ident, token_id, sign_b64u, exp, chain_id)
+			VALUES ($1, $2, $3, $4, $5) RETURNING id",
+		)
+		.bind(token_c.user_ident)
+		.bind(token_c.token_id)
+		.bind(token_c.sign_b64u)
+		.bind(token_c.exp)
+		.bind(token_c.chain_id)
+		.fetch_one(db)
+		.await?;
+
+		Ok(id)
+	}
+
+	async fn get_by_token_id(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		token_id: &str,
+	) -> Result<TokenRecord> {
+		let db = mm.db();
+
+		let record: TokenRecord =
+			sqlx::query_as("SELECT * FROM token WHERE token_id = $1")
+				.bind(token_id)
+				.fetch_optional(db)
+				.await?
+				.ok_or(Error::TokenNotFound)?;
+
+		Ok(record)
+	}
+
+	async fn mark_used(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		let db = mm.db();
+
+		sqlx::query("UPDATE token SET used = TRUE WHERE id = $1")
+			.bind(id)
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	/// Revoke every token in a chain — used when a consumed refresh token is
+	/// replayed, which signals the chain has leaked.
+	async fn revoke_chain(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		chain_id: &str,
+	) -> Result<()> {
+		let db = mm.db();
+
+		sqlx::query("UPDATE token SET revoked = TRUE WHERE chain_id = $1")
+			.bind(chain_id)
+			.execute(db)
+			.await?;
+
+		Ok(())
+	}
+
+	/// Flag the stored token row matching a `(user_ident, sign_b64u)` pair as
+	/// revoked. A pair with no row in the `token` table is not recorded.
+	async fn revoke(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		user_ident: &str,
+		sign_b64u: &str,
+	) -> Result<()> {
+		let db = mm.db();
+
+		sqlx::query(
+			"UPDATE token SET revoked = TRUE
+			WHERE user_ident = $1 AND sign_b64u = $2",
+		)
+		.bind(user_ident)
+		.bind(sign_b64u)
+		.execute(db)
+		.await?;
+
+		Ok(())
+	}
+
+	/// Whether a `(user_ident, sign_b64u)` pair is flagged revoked in the DB.
+	async fn is_revoked(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		user_ident: &str,
+		sign_b64u: &str,
+	) -> Result<bool> {
+		let db = mm.db();
+
+		let (revoked,): (bool,) = sqlx::query_as(
+			"SELECT EXISTS(
+				SELECT 1 FROM token
+				WHERE user_ident = $1 AND sign_b64u = $2 AND revoked = TRUE
+			)",
+		)
+		.bind(user_ident)
+		.bind(sign_b64u)
+		.fetch_one(db)
+		.await?;
+
+		Ok(revoked)
+	}
+}
+
+// endregion:   --- TokenBmc
+
+// region:      --- Refresh Token Gen and Rotation
+
+/// Mint a long-lived refresh token for `user`, starting a fresh chain, and
+/// return its on-wire string form.
+pub async fn generate_refresh_token(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	user: &str,
+	salt: &str,
+) -> Result<String> {
+	_generate_refresh_token(ctx, mm, user, salt, Uuid::new_v4().to_string()).await
+}
+
+/// Validate a presented refresh token and, on success, consume it (one-time
+/// use) and issue a fresh web token plus a new refresh token in the same
+/// chain. Replaying an already-consumed token revokes the whole chain and
+/// returns [`Error::TokenReused`].
+pub async fn rotate_refresh_token(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	refresh_token: &str,
+	salt: &str,
+) -> Result<(Token, String)> {
+	let presented: Token = refresh_token.parse()?;
+	let record = TokenBmc::get_by_token_id(ctx, mm, &presented.ident).await?;
+
+	// -- One-time-use: a consumed or revoked token being presented again means
+	//    the chain has leaked; nuke it.
+	if record.used || record.revoked {
+		TokenBmc::revoke_chain(ctx, mm, &record.chain_id).await?;
+		return Err(Error::TokenReused);
+	}
+
+	// -- Validate signature/expiry and that it matches the stored signature.
+	token::validate_token(&presented, salt)?;
+	if presented.sign_b64u != record.sign_b64u {
+		TokenBmc::revoke_chain(ctx, mm, &record.chain_id).await?;
+		return Err(Error::TokenReused);
+	}
+
+	// -- Consume and rotate.
+	TokenBmc::mark_used(ctx, mm, record.id).await?;
+	let web_token = token::generate_web_token(&record.user_ident, salt)?;
+	let new_refresh = _generate_refresh_token(
+		ctx,
+		mm,
+		&record.user_ident,
+		salt,
+		record.chain_id,
+	)
+	.await?;
+
+	Ok((web_token, new_refresh))
+}
+
+// endregion:   --- Refresh Token Gen and Rotation
+
+// region:      --- Revocation Denylist
+
+/// Revoke a `(user_ident, sign_b64u)` pair: add it to the in-process fast-path
+/// cache so the current process rejects it immediately, and set the persisted
+/// `revoked` flag on the matching `token` row. Only refresh tokens are stored
+/// in that table, so for any other pair (a web token, for example) the
+/// revocation is held in the in-process cache only.
+pub async fn revoke_token(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	user_ident: &str,
+	sign_b64u: &str,
+) -> Result<()> {
+	TokenBmc::revoke(ctx, mm, user_ident, sign_b64u).await?;
+	token::revoke_token(user_ident, sign_b64u);
+	Ok(())
+}
+
+/// Whether a `(user_ident, sign_b64u)` pair is revoked, consulting both the
+/// persisted DB denylist and the in-process fast-path cache.
+pub async fn is_revoked(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	user_ident: &str,
+	sign_b64u: &str,
+) -> Result<bool> {
+	Ok(token::is_revoked(user_ident, sign_b64u)
+		|| TokenBmc::is_revoked(ctx, mm, user_ident, sign_b64u).await?)
+}
+
+/// Validate a web token's signature/expiry and then consult the persisted
+/// denylist — the DB-backed counterpart to [`crypt::token::validate_web_token`]
+/// for callers that hold a [`ModelManager`].
+pub async fn validate_web_token(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	web_token: &Token,
+	salt: &str,
+) -> Result<()> {
+	token::validate_web_token(web_token, salt)?;
+
+	if is_revoked(ctx, mm, &web_token.ident, &web_token.sign_b64u).await? {
+		return Err(Error::TokenRevoked);
+	}
+
+	Ok(())
+}
+
+// endregion:   --- Revocation Denylist
+
+// region:      --- (private) Refresh Token Helpers
+
+async fn _generate_refresh_token(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	user: &str,
+	salt: &str,
+	chain_id: String,
+) -> Result<String> {
+	let token_id = Uuid::new_v4().to_string();
+	let refresh = token::generate_token(&token_id, REFRESH_DURATION_SEC, salt)?;
+
+	TokenBmc::create(
+		ctx,
+		mm,
+		TokenForCreate {
+			user_ident: user.to_string(),
+			token_id,
+			sign_b64u: refresh.sign_b64u.clone(),
+			exp: refresh.exp.clone(),
+			chain_id,
+		},
+	)
+	.await?;
+
+	Ok(refresh.to_string())
+}
+
+// endregion:   --- (private) Refresh Token Helpers
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rotate_refresh_token_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_user = "test_rotate_refresh_token_ok user";
+		let fx_salt = "pepper";
+
+		// -- Exec
+		let refresh =
+			generate_refresh_token(&ctx, &mm, fx_user, fx_salt).await?;
+		let (_web_token, new_refresh) =
+			rotate_refresh_token(&ctx, &mm, &refresh, fx_salt).await?;
+
+		// -- Check: rotation issues a brand-new refresh token...
+		assert_ne!(refresh, new_refresh, "refresh token should rotate");
+		// ...which can itself be rotated.
+		rotate_refresh_token(&ctx, &mm, &new_refresh, fx_salt).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rotate_refresh_token_replay_revokes_chain() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_user = "test_rotate_refresh_token_replay user";
+		let fx_salt = "pepper";
+
+		// -- Exec: consume the refresh token once.
+		let refresh =
+			generate_refresh_token(&ctx, &mm, fx_user, fx_salt).await?;
+		let (_web_token, new_refresh) =
+			rotate_refresh_token(&ctx, &mm, &refresh, fx_salt).await?;
+
+		// -- Check: replaying the consumed token trips `TokenReused`...
+		let res = rotate_refresh_token(&ctx, &mm, &refresh, fx_salt).await;
+		assert!(
+			matches!(res, Err(Error::TokenReused)),
+			"replay should match `Err(Error::TokenReused)` but was `{res:?}`"
+		);
+
+		// ...and revokes the whole chain, so the rotated-out token is dead too.
+		let res = rotate_refresh_token(&ctx, &mm, &new_refresh, fx_salt).await;
+		assert!(
+			matches!(res, Err(Error::TokenReused)),
+			"chain should be revoked; was `{res:?}`"
+		);
+
+		Ok(())
+	}
+}